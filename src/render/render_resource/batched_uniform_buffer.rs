@@ -7,6 +7,7 @@ use bevy::render::{
     },
     renderer::{RenderDevice, RenderQueue},
 };
+use nonmax::NonMaxU32;
 use std::{marker::PhantomData, num::NonZeroU64};
 use wgpu::{BindingResource, Limits};
 
@@ -22,8 +23,11 @@ const MAX_REASONABLE_UNIFORM_BUFFER_BINDING_SIZE: u32 = 1 << 20;
 /// are grouped into a batch as an `array<T, N>` in WGSL.
 ///
 /// This reduces the number of rebindings required due to having to pass dynamic
-/// offsets to bind group commands, and if indices into the array can be passed
-/// in via other means, it enables batching of draw commands.
+/// offsets to bind group commands. Elements pushed into the same batch share a
+/// [`GpuListIndex::dynamic_offset`], so a queue system that sorts its draw items by
+/// pipeline, bind group and [`GpuListIndex::batch_key`] can fold a run of consecutive
+/// shapes sharing a batch into a single draw command, reading each shape's data via
+/// `GpuListIndex.index` in the shader instead of rebinding per shape.
 pub struct BatchedUniformBuffer<T: GpuListable> {
     uniforms: DynamicUniformBuffer<MaxCapacityArray<Vec<T>>>,
     temp: MaxCapacityArray<Vec<T>>,
@@ -64,7 +68,8 @@ impl<T: GpuListable> BatchedUniformBuffer<T> {
 
     pub fn push(&mut self, component: T) -> GpuListIndex<T> {
         let result = GpuListIndex {
-            index: self.temp.0.len() as u32,
+            index: NonMaxU32::new(self.temp.0.len() as u32)
+                .expect("a batch should never hold u32::MAX elements"),
             dynamic_offset: Some(self.current_offset),
             element_type: PhantomData,
         };
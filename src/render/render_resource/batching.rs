@@ -0,0 +1,205 @@
+use std::ops::Range;
+
+use super::{GpuList, GpuListIndex, GpuListable};
+
+/// One contiguous run of instances sharing a pipeline, bind group and [`GpuListIndex::batch_key`],
+/// drawable with a single `draw(0..vertices, first_instance..first_instance + instance_count)`
+/// call (or a single [`DrawIndirectArgs`](super::DrawIndirectArgs) entry) instead of one draw
+/// call per shape.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawBatch {
+    pub batch_key: u32,
+    pub first_instance: u32,
+    pub instance_count: u32,
+}
+
+impl DrawBatch {
+    #[inline]
+    pub fn instance_range(&self) -> Range<u32> {
+        self.first_instance..self.first_instance + self.instance_count
+    }
+}
+
+/// Collapses a sequence of shapes into the smallest number of [`DrawBatch`]es.
+///
+/// `items` must already be in the order the render phase will draw them: sorted by whatever
+/// distinguishes draws that can't share a batch despite an equal `batch_key` (pipeline, bind
+/// group, ...) and, within a run of equal `sort_key`s, by the order the shapes were
+/// [`push`](super::GpuList::push)ed. This function only merges *consecutive* entries — it
+/// doesn't reorder anything itself.
+///
+/// Two consecutive entries fold into the same [`DrawBatch`] when their `sort_key` and
+/// [`GpuListIndex::batch_key`] both match and their instance indices are contiguous. The index
+/// contiguity only holds if shapes were pushed onto the backing [`GpuList`](super::GpuList) in
+/// the same relative order `items` is sorted in, since `push` assigns indices sequentially.
+pub fn batch_draws<K: PartialEq, T: GpuListable>(
+    items: impl IntoIterator<Item = (K, GpuListIndex<T>)>,
+) -> Vec<DrawBatch> {
+    let mut batches: Vec<(K, DrawBatch)> = Vec::new();
+
+    for (sort_key, index) in items {
+        let instance = index.index.get();
+        let batch_key = index.batch_key();
+
+        if let Some((last_key, batch)) = batches.last_mut() {
+            if *last_key == sort_key
+                && batch.batch_key == batch_key
+                && batch.first_instance + batch.instance_count == instance
+            {
+                batch.instance_count += 1;
+                continue;
+            }
+        }
+
+        batches.push((
+            sort_key,
+            DrawBatch {
+                batch_key,
+                first_instance: instance,
+                instance_count: 1,
+            },
+        ));
+    }
+
+    batches.into_iter().map(|(_, batch)| batch).collect()
+}
+
+/// Pushes `values` (already sorted the way the render phase will draw them, see [`batch_draws`])
+/// onto `list`, then folds the resulting indices straight into [`DrawBatch`]es.
+///
+/// Matches `list`'s backend once via [`GpuList::as_uniform_mut`]/[`GpuList::as_storage_mut`] and
+/// pushes every value through the same concrete `push`, rather than re-matching the enum per
+/// shape. A [`GpuList::External`] list owns its buffer itself, so there's nothing to push here —
+/// this returns an empty batch list for it rather than panicking.
+pub fn push_and_batch<K: PartialEq, T: GpuListable>(
+    list: &mut GpuList<T>,
+    values: impl IntoIterator<Item = (K, T)>,
+) -> Vec<DrawBatch> {
+    let indices: Vec<(K, GpuListIndex<T>)> = if let Some(uniform) = list.as_uniform_mut() {
+        values
+            .into_iter()
+            .map(|(key, value)| (key, uniform.push(value)))
+            .collect()
+    } else if let Some(storage) = list.as_storage_mut() {
+        values
+            .into_iter()
+            .map(|(key, value)| (key, storage.push(value)))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    batch_draws(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::marker::PhantomData;
+
+    use bevy::render::render_resource::ShaderType;
+    use nonmax::NonMaxU32;
+
+    #[derive(Clone, Copy, ShaderType, bytemuck::Pod, bytemuck::Zeroable)]
+    #[repr(C)]
+    struct TestInstance {
+        value: f32,
+    }
+
+    fn index(i: u32, dynamic_offset: Option<u32>) -> GpuListIndex<TestInstance> {
+        GpuListIndex {
+            index: NonMaxU32::new(i).unwrap(),
+            dynamic_offset,
+            element_type: PhantomData,
+        }
+    }
+
+    #[test]
+    fn collapses_a_contiguous_run_into_one_batch() {
+        let items = vec![
+            ("pipeline_a", index(0, Some(0))),
+            ("pipeline_a", index(1, Some(0))),
+            ("pipeline_a", index(2, Some(0))),
+        ];
+
+        assert_eq!(
+            batch_draws(items),
+            vec![DrawBatch {
+                batch_key: 0,
+                first_instance: 0,
+                instance_count: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn splits_on_sort_key_change() {
+        let items = vec![
+            ("pipeline_a", index(0, Some(0))),
+            ("pipeline_a", index(1, Some(0))),
+            ("pipeline_b", index(2, Some(0))),
+        ];
+
+        assert_eq!(
+            batch_draws(items),
+            vec![
+                DrawBatch { batch_key: 0, first_instance: 0, instance_count: 2 },
+                DrawBatch { batch_key: 0, first_instance: 2, instance_count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_on_batch_key_change_even_with_the_same_sort_key() {
+        let items = vec![
+            ("pipeline_a", index(0, Some(0))),
+            ("pipeline_a", index(1, Some(256))),
+        ];
+
+        assert_eq!(
+            batch_draws(items),
+            vec![
+                DrawBatch { batch_key: 0, first_instance: 0, instance_count: 1 },
+                DrawBatch { batch_key: 256, first_instance: 1, instance_count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_on_non_contiguous_instance_index() {
+        let items = vec![
+            ("pipeline_a", index(0, Some(0))),
+            ("pipeline_a", index(2, Some(0))),
+        ];
+
+        assert_eq!(
+            batch_draws(items),
+            vec![
+                DrawBatch { batch_key: 0, first_instance: 0, instance_count: 1 },
+                DrawBatch { batch_key: 0, first_instance: 2, instance_count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn push_and_batch_drives_a_run_of_shapes_down_to_one_batch() {
+        use wgpu::Limits;
+
+        use super::super::BatchedUniformBuffer;
+
+        let mut list: GpuList<TestInstance> =
+            GpuList::Uniform(BatchedUniformBuffer::new(&Limits::default()));
+
+        let values = (0..8).map(|i| ("pipeline_a", TestInstance { value: i as f32 }));
+        let batches = push_and_batch(&mut list, values);
+
+        assert_eq!(
+            batches,
+            vec![DrawBatch {
+                batch_key: 0,
+                first_instance: 0,
+                instance_count: 8,
+            }]
+        );
+    }
+}
@@ -1,18 +1,38 @@
 use bevy::{
     prelude::*,
     render::{
-        render_resource::{encase::private::WriteInto, ShaderSize, ShaderType, StorageBuffer},
+        render_resource::{encase::private::WriteInto, Buffer, ShaderSize, ShaderType},
         renderer::{RenderDevice, RenderQueue},
     },
 };
-use std::{marker::PhantomData, mem};
-use wgpu::{BindGroupLayoutEntry, BindingResource, BindingType, BufferBindingType, ShaderStages};
+use nonmax::NonMaxU32;
+use std::marker::PhantomData;
+use wgpu::{
+    BindGroupLayoutEntry, BindingResource, BindingType, BufferBindingType, BufferUsages,
+    ShaderStages,
+};
 
-use super::BatchedUniformBuffer;
+use super::{BatchedUniformBuffer, StagedBuffer};
 
 /// Trait for types able to go in a [`GpuList`].
-pub trait GpuListable: ShaderType + ShaderSize + WriteInto + Clone {}
-impl<T: ShaderType + ShaderSize + WriteInto + Clone> GpuListable for T {}
+///
+/// The `bytemuck::Pod` bound exists for [`GpuList::Storage`]: its elements are `bytemuck`-cast
+/// and copied byte-for-byte into the GPU buffer, with no `encase` serialization pass. `encase`'s
+/// `ShaderType`/`WriteInto` impl still governs the std430 layout the shader actually reads, so
+/// implementers must ensure their `Pod` (native Rust) layout already matches that std430 layout
+/// — e.g. by using `#[repr(C)]` with explicit padding fields matching WGSL's alignment rules.
+/// A type whose derived `ShaderType` impl inserts padding the native layout doesn't have would be
+/// uploaded with the wrong bytes in the wrong places; [`GpuListStorage::new`] asserts
+/// `size_of::<T>() == T::min_size()` to catch the common case of that mismatch loudly instead of
+/// letting it render garbage silently.
+pub trait GpuListable: ShaderType + ShaderSize + WriteInto + Clone + bytemuck::Pod {}
+impl<T: ShaderType + ShaderSize + WriteInto + Clone + bytemuck::Pod> GpuListable for T {}
+
+/// The size of each chunk allocated by a [`GpuListStorage`]'s staging belt.
+const STAGING_BELT_CHUNK_SIZE: u64 = 1 << 16;
+/// The smallest storage buffer we'll allocate, to avoid churning tiny buffers as a list grows
+/// from empty.
+const MIN_STORAGE_BUFFER_SIZE: u64 = 256;
 
 /// Stores a list of elements to be transferred to the GPU and made accessible to shaders as a read-only array.
 ///
@@ -20,6 +40,12 @@ impl<T: ShaderType + ShaderSize + WriteInto + Clone> GpuListable for T {}
 /// Otherwise, this falls back to a dynamic offset uniform buffer with the largest
 /// array of T that fits within a uniform buffer binding.
 ///
+/// A [`GpuList::External`] list instead binds a buffer the caller keeps up to date itself, for
+/// callers that populate instance data outside of per-shape `push` calls (a compute shader, or
+/// a single bulk upload from a storage buffer asset) — `clear` and `write_buffer` are no-ops on
+/// it, since there's nothing for this resource to own or upload, but `push` panics: there's no
+/// sensible index to hand back for data this resource doesn't store.
+///
 /// Other options for storing GPU-accessible data are:
 /// * [`StorageBuffer`](crate::render_resource::StorageBuffer)
 /// * [`DynamicStorageBuffer`](crate::render_resource::DynamicStorageBuffer)
@@ -30,7 +56,8 @@ impl<T: ShaderType + ShaderSize + WriteInto + Clone> GpuListable for T {}
 #[derive(Resource)]
 pub enum GpuList<T: GpuListable> {
     Uniform(BatchedUniformBuffer<T>),
-    Storage((StorageBuffer<Vec<T>>, Vec<T>)),
+    Storage(GpuListStorage<T>),
+    External(Buffer, PhantomData<T>),
 }
 
 impl<T: GpuListable> GpuList<T> {
@@ -39,39 +66,75 @@ impl<T: GpuListable> GpuList<T> {
         if limits.max_storage_buffers_per_shader_stage > 0 {
             GpuList::Uniform(BatchedUniformBuffer::new(&limits))
         } else {
-            GpuList::Storage((StorageBuffer::default(), Vec::new()))
+            GpuList::Storage(GpuListStorage::new())
         }
     }
 
+    /// Binds to a buffer the caller populates and keeps up to date itself, instead of one this
+    /// `GpuList` fills via `push`. The render pipeline binds `buffer` directly; `clear` and
+    /// `write_buffer` are no-ops for the list returned here, and `push` panics — pushing onto a
+    /// caller-owned list is a programmer error, not something to silently swallow.
+    ///
+    /// `buffer` must already be at least `T::min_size()` bytes — [`binding_layout`](Self::binding_layout)
+    /// advertises that as the binding's minimum size for its storage-buffer layout, and a smaller
+    /// buffer fails bind-group creation there at runtime with no context pointing back here.
+    pub fn from_external_buffer(buffer: Buffer) -> Self {
+        assert!(
+            buffer.size() >= T::min_size().get(),
+            "external GpuList buffer ({} bytes) is smaller than one element's std430 size ({} \
+             bytes); size it for at least one element before binding",
+            buffer.size(),
+            T::min_size().get(),
+        );
+        GpuList::External(buffer, PhantomData)
+    }
+
     pub fn clear(&mut self) {
         match self {
             GpuList::Uniform(buffer) => buffer.clear(),
-            GpuList::Storage((_, buffer)) => buffer.clear(),
+            GpuList::Storage(storage) => storage.values.clear(),
+            GpuList::External(..) => {}
         }
     }
 
     pub fn push(&mut self, value: T) -> GpuListIndex<T> {
         match self {
             GpuList::Uniform(buffer) => buffer.push(value),
-            GpuList::Storage((_, buffer)) => {
-                let index = buffer.len() as u32;
-                buffer.push(value);
-                GpuListIndex {
-                    index,
-                    dynamic_offset: None,
-                    element_type: PhantomData,
-                }
+            GpuList::Storage(storage) => storage.push(value),
+            GpuList::External(..) => {
+                panic!("cannot push onto a GpuList::External list; the caller owns its buffer")
             }
         }
     }
 
+    /// Returns the uniform-backed buffer if this list fell back to dynamic-offset uniform
+    /// buffers. Lets a queue system match on the backend once per frame and then push every
+    /// shape through the same concrete [`BatchedUniformBuffer::push`], instead of re-matching
+    /// this enum on every shape.
+    #[inline]
+    pub fn as_uniform_mut(&mut self) -> Option<&mut BatchedUniformBuffer<T>> {
+        match self {
+            GpuList::Uniform(buffer) => Some(buffer),
+            _ => None,
+        }
+    }
+
+    /// Returns the storage-backed buffer if this list is using a GPU storage buffer, for the
+    /// same per-frame-match, per-shape-monomorphized pattern as
+    /// [`as_uniform_mut`](Self::as_uniform_mut).
+    #[inline]
+    pub fn as_storage_mut(&mut self) -> Option<&mut GpuListStorage<T>> {
+        match self {
+            GpuList::Storage(storage) => Some(storage),
+            _ => None,
+        }
+    }
+
     pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
         match self {
             GpuList::Uniform(buffer) => buffer.write_buffer(device, queue),
-            GpuList::Storage((buffer, vec)) => {
-                buffer.set(mem::take(vec));
-                buffer.write_buffer(device, queue);
-            }
+            GpuList::Storage(storage) => storage.write_buffer(device, queue),
+            GpuList::External(..) => {}
         }
     }
 
@@ -105,7 +168,8 @@ impl<T: GpuListable> GpuList<T> {
     pub fn binding(&self) -> Option<BindingResource> {
         match self {
             GpuList::Uniform(buffer) => buffer.binding(),
-            GpuList::Storage((buffer, _)) => buffer.binding(),
+            GpuList::Storage(storage) => storage.binding(),
+            GpuList::External(buffer, ..) => Some(buffer.as_entire_binding()),
         }
     }
 
@@ -119,13 +183,132 @@ impl<T: GpuListable> GpuList<T> {
     }
 }
 
+/// Storage-buffer backing for [`GpuList`].
+///
+/// Elements are accumulated into a plain `Vec` as they're pushed, then [`write_buffer`](Self::write_buffer)
+/// stages them through a [`StagedBuffer`] instead of reserializing the whole array through
+/// `encase` every frame.
+pub struct GpuListStorage<T: GpuListable> {
+    values: Vec<T>,
+    buffer: StagedBuffer,
+    element_type: PhantomData<T>,
+}
+
+impl<T: GpuListable> GpuListStorage<T> {
+    fn new() -> Self {
+        // GpuListStorage raw-copies `T`'s native bytes into the storage buffer (see
+        // `GpuListable`'s doc comment) instead of serializing through `encase` — that's only
+        // sound if `T`'s `#[repr(C)]` size already matches its std430 `ShaderSize`. Check once
+        // here, at list construction, rather than on every push/write_buffer call.
+        assert_eq!(
+            std::mem::size_of::<T>() as u64,
+            T::min_size().get(),
+            "{}'s native size ({} bytes) doesn't match its std430 ShaderSize ({} bytes); its \
+             #[repr(C)] layout must match the std430 layout its ShaderType impl (and the shader \
+             reading it) expect, or GpuList::Storage will upload every element after the first \
+             at the wrong offset",
+            std::any::type_name::<T>(),
+            std::mem::size_of::<T>(),
+            T::min_size().get(),
+        );
+
+        Self {
+            values: Vec::new(),
+            buffer: StagedBuffer::new(
+                STAGING_BELT_CHUNK_SIZE,
+                BufferUsages::STORAGE | BufferUsages::COPY_DST,
+                "gpu_list_storage_buffer",
+                MIN_STORAGE_BUFFER_SIZE,
+            ),
+            element_type: PhantomData,
+        }
+    }
+
+    fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        self.buffer.write(device, queue, &self.values);
+    }
+
+    fn binding(&self) -> Option<BindingResource> {
+        self.buffer.binding()
+    }
+
+    pub fn push(&mut self, value: T) -> GpuListIndex<T> {
+        let index = self.values.len() as u32;
+        self.values.push(value);
+        GpuListIndex {
+            index: NonMaxU32::new(index).expect("GpuList should never hold u32::MAX elements"),
+            dynamic_offset: None,
+            element_type: PhantomData,
+        }
+    }
+}
+
 /// An index into a [`GpuList`] for a given element.
+///
+/// `index` uses [`NonMaxU32`] rather than `u32` so this component gains a niche, letting it sit
+/// in an `Option<GpuListIndex<T>>` (e.g. a relationship component) at no extra size.
 #[derive(Component)]
 pub struct GpuListIndex<T: GpuListable> {
     /// The index to use in a shader into the array.
-    pub index: u32,
+    pub index: NonMaxU32,
     /// The dynamic offset to use when setting the bind group in a pass.
     /// Only used on platforms that don't support storage buffers.
     pub dynamic_offset: Option<u32>,
     pub element_type: PhantomData<T>,
 }
+
+impl<T: GpuListable> GpuListIndex<T> {
+    /// Key used to group consecutive shapes that can be folded into a single draw call.
+    ///
+    /// Shapes with the same pipeline, bind group and `batch_key` sit in the same
+    /// [`BatchedUniformBuffer`] chunk (or, on storage-buffer platforms, the same backing
+    /// buffer) and can be drawn with one `draw(0..vertices, first_instance..first_instance + count)`
+    /// call spanning their contiguous instance range instead of one draw call each.
+    #[inline]
+    pub fn batch_key(&self) -> u32 {
+        self.dynamic_offset.unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::render::render_resource::ShaderType;
+    use wgpu::Limits;
+
+    #[derive(Clone, Copy, ShaderType, bytemuck::Pod, bytemuck::Zeroable)]
+    #[repr(C)]
+    struct TestInstance {
+        value: f32,
+    }
+
+    #[test]
+    fn as_uniform_mut_only_matches_the_uniform_backend() {
+        let mut list: GpuList<TestInstance> =
+            GpuList::Uniform(BatchedUniformBuffer::new(&Limits::default()));
+        assert!(list.as_uniform_mut().is_some());
+        assert!(list.as_storage_mut().is_none());
+    }
+
+    #[test]
+    fn as_storage_mut_only_matches_the_storage_backend() {
+        let mut list: GpuList<TestInstance> = GpuList::Storage(GpuListStorage::new());
+        assert!(list.as_storage_mut().is_some());
+        assert!(list.as_uniform_mut().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "native size")]
+    fn storage_rejects_a_type_whose_native_size_does_not_match_its_std430_size() {
+        #[derive(Clone, Copy, ShaderType, bytemuck::Pod, bytemuck::Zeroable)]
+        #[repr(C)]
+        struct Mismatched {
+            // std430 gives a lone vec3 field 16-byte alignment and rounds the struct's size up
+            // to 16 to match, but its native #[repr(C)] size is only 12 — the exact mismatch
+            // GpuListStorage::new should catch.
+            value: Vec3,
+        }
+
+        let _ = GpuListStorage::<Mismatched>::new();
+    }
+}
@@ -0,0 +1,139 @@
+use std::ops::Range;
+
+use bevy::render::{
+    render_resource::Buffer,
+    renderer::{RenderDevice, RenderQueue},
+};
+use bytemuck::{Pod, Zeroable};
+use wgpu::{BufferUsages, Features};
+
+use super::{DrawBatch, StagedBuffer};
+
+const STAGING_BELT_CHUNK_SIZE: u64 = 1 << 14;
+const MIN_INDIRECT_BUFFER_SIZE: u64 = 256;
+
+/// Mirrors `wgpu::util::DrawIndirectArgs`, laid out as `Pod` so it can be staged through a
+/// [`StagedBuffer`] the same way `GpuListStorage` stages instance data.
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct DrawIndirectArgs {
+    pub vertex_count: u32,
+    pub instance_count: u32,
+    pub first_vertex: u32,
+    pub first_instance: u32,
+}
+
+/// Whether `device` can issue a `multi_draw_indirect` call with non-zero `first_instance`.
+///
+/// Every batch after the first one this path draws has a non-zero `first_instance` (it's how one
+/// pipeline's batches each index into their own slice of the instance buffer), which needs
+/// `Features::INDIRECT_FIRST_INSTANCE` in addition to `Features::MULTI_DRAW_INDIRECT` — a device
+/// reporting the latter without the former would otherwise hit wgpu's indirect-first-instance
+/// validation error at submit time, the exact failure this check exists to avoid.
+///
+/// A queue system should check this once and either pack [`DrawIndirectArgs`] into an
+/// [`IndirectBuffer`] and issue a single `multi_draw_indirect`, or fall back to one `draw` call
+/// per batch when the features aren't reported.
+pub fn supports_multi_draw_indirect(device: &RenderDevice) -> bool {
+    device
+        .features()
+        .contains(Features::MULTI_DRAW_INDIRECT | Features::INDIRECT_FIRST_INSTANCE)
+}
+
+/// Packs a run of per-batch [`DrawIndirectArgs`] into an `INDIRECT | COPY_DST` buffer for a
+/// single `multi_draw_indirect` call per pipeline, staged through a [`StagedBuffer`] rather
+/// than reallocating the buffer every frame.
+pub struct IndirectBuffer {
+    args: Vec<DrawIndirectArgs>,
+    buffer: StagedBuffer,
+}
+
+impl IndirectBuffer {
+    pub fn new() -> Self {
+        Self {
+            args: Vec::new(),
+            buffer: StagedBuffer::new(
+                STAGING_BELT_CHUNK_SIZE,
+                BufferUsages::INDIRECT | BufferUsages::COPY_DST,
+                "gpu_list_indirect_buffer",
+                MIN_INDIRECT_BUFFER_SIZE,
+            ),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.args.clear();
+    }
+
+    /// Appends one batch's draw args, returning its index into the buffer, or `None` if
+    /// `first_instance + instance_count` would overrun `instance_buffer_len` (the number of
+    /// elements in the `GpuList` instance buffer this batch draws from) — wgpu raises an
+    /// indirect-overrun validation error for that at submit time, so we catch and skip the bad
+    /// batch here instead of letting one malformed batch take down the whole frame's draws.
+    /// Uses `u64` arithmetic so a pathological `first_instance` can't wrap `u32` and slip past
+    /// the check.
+    pub fn push(&mut self, args: DrawIndirectArgs, instance_buffer_len: u32) -> Option<u32> {
+        let end = args.first_instance as u64 + args.instance_count as u64;
+        if end > instance_buffer_len as u64 {
+            return None;
+        }
+
+        let index = self.args.len() as u32;
+        self.args.push(args);
+        Some(index)
+    }
+
+    pub fn write_buffer(&mut self, device: &RenderDevice, queue: &RenderQueue) {
+        self.buffer.write(device, queue, &self.args);
+    }
+
+    /// The buffer and draw count to pass to `multi_draw_indirect`, or `None` if nothing was
+    /// pushed this frame.
+    pub fn draws(&self) -> Option<(&Buffer, u32)> {
+        self.buffer
+            .buffer()
+            .filter(|_| !self.args.is_empty())
+            .map(|buffer| (buffer, self.args.len() as u32))
+    }
+}
+
+impl Default for IndirectBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Packs one mesh's [`DrawBatch`]es (all drawing `vertex_range` out of that mesh) into `indirect`
+/// as [`DrawIndirectArgs`], and reports whether the caller can fold them into a single
+/// `multi_draw_indirect` call or must fall back to one draw per batch.
+///
+/// This is the piece of the request [`batch_draws`](super::batch_draws)'s output still needs to
+/// turn into actual draw commands: a queue system calls it once per (pipeline, mesh) after
+/// collapsing that pipeline's shapes into batches, then either issues
+/// `multi_draw_indirect(buffer, 0, count)` against [`IndirectBuffer::draws`] when this returns
+/// `true`, or draws each batch's [`DrawBatch::instance_range`] directly when it returns `false`.
+pub fn prepare_indirect_draws(
+    device: &RenderDevice,
+    indirect: &mut IndirectBuffer,
+    batches: &[DrawBatch],
+    vertex_range: Range<u32>,
+    instance_buffer_len: u32,
+) -> bool {
+    if !supports_multi_draw_indirect(device) {
+        return false;
+    }
+
+    for batch in batches {
+        indirect.push(
+            DrawIndirectArgs {
+                vertex_count: vertex_range.end - vertex_range.start,
+                instance_count: batch.instance_count,
+                first_vertex: vertex_range.start,
+                first_instance: batch.first_instance,
+            },
+            instance_buffer_len,
+        );
+    }
+
+    true
+}
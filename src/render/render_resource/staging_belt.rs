@@ -0,0 +1,229 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::render::{
+    render_resource::Buffer,
+    renderer::{RenderDevice, RenderQueue},
+};
+use wgpu::{
+    BindingResource, BufferDescriptor, BufferUsages, CommandEncoder, CommandEncoderDescriptor,
+    MapMode,
+};
+
+/// Minimum alignment handed out for each staged write, matching the alignment wgpu requires
+/// for `copy_buffer_to_buffer` source/destination offsets on every backend.
+const CHUNK_ALIGNMENT: u64 = 256;
+
+struct OpenChunk {
+    buffer: Buffer,
+    size: u64,
+    offset: u64,
+}
+
+/// A pool of `MAP_WRITE | COPY_SRC` buffers used to stage per-frame writes into a GPU-side
+/// buffer without allocating an intermediate CPU `Vec` or going through a serialization pass
+/// every frame.
+///
+/// This mirrors the pattern used by `wgpu::util::StagingBelt`: [`GpuStagingBelt::write`] fills a
+/// mapped, 256-byte-aligned slice of a chunk directly (typically by `bytemuck`-casting it and
+/// copying a `&[T]` in) and records a `copy_buffer_to_buffer` from that slice into the
+/// destination buffer on the current encoder. Call [`GpuStagingBelt::finish`] once all of this
+/// frame's writes are recorded, *before* submitting the command buffer that contains them — this
+/// unmaps every chunk that was written to, which wgpu requires before it can appear in a
+/// submitted command buffer. After submission, call [`GpuStagingBelt::recall`] to start
+/// remapping those (now-unmapped) chunks in the background so they're ready to hand out again.
+pub struct GpuStagingBelt {
+    chunk_size: u64,
+    free: Vec<Buffer>,
+    /// Buffers written to this frame, awaiting `recall` once their copies have been submitted.
+    in_flight: Vec<Buffer>,
+    /// Buffers whose `map_async` callback has fired and are ready to be reused.
+    recycled: Arc<Mutex<Vec<Buffer>>>,
+    open: Option<OpenChunk>,
+}
+
+impl GpuStagingBelt {
+    /// Creates a belt that allocates new chunks at least `chunk_size` bytes at a time.
+    pub fn new(chunk_size: u64) -> Self {
+        Self {
+            chunk_size: chunk_size.max(CHUNK_ALIGNMENT),
+            free: Vec::new(),
+            in_flight: Vec::new(),
+            recycled: Arc::new(Mutex::new(Vec::new())),
+            open: None,
+        }
+    }
+
+    /// Stages `data` and records a copy of it into `target` at `target_offset` (in bytes) on
+    /// `encoder`. Opens a new chunk from the free pool (or allocates one) if the currently open
+    /// chunk doesn't have enough room left.
+    ///
+    /// `T`'s `bytemuck::Pod` byte layout is copied verbatim into the destination buffer, with no
+    /// conversion — the caller is responsible for `T`'s native layout matching the std140/std430
+    /// layout its `ShaderType` impl (and the shader reading it) expect.
+    pub fn write<T: bytemuck::Pod>(
+        &mut self,
+        device: &RenderDevice,
+        encoder: &mut CommandEncoder,
+        target: &Buffer,
+        target_offset: u64,
+        data: &[T],
+    ) {
+        let bytes = bytemuck::cast_slice(data);
+        let size = bytes.len() as u64;
+        if size == 0 {
+            return;
+        }
+
+        let aligned_offset = self
+            .open
+            .as_ref()
+            .map(|chunk| round_up(chunk.offset, CHUNK_ALIGNMENT))
+            .unwrap_or(0);
+
+        if self
+            .open
+            .as_ref()
+            .map_or(true, |chunk| aligned_offset + size > chunk.size)
+        {
+            self.open_chunk(device, size);
+        }
+
+        let chunk = self.open.as_mut().expect("chunk was just opened");
+        let offset = round_up(chunk.offset, CHUNK_ALIGNMENT);
+        {
+            let mut view = chunk.buffer.slice(offset..offset + size).get_mapped_range_mut();
+            view.copy_from_slice(bytes);
+        }
+        chunk.offset = offset + size;
+
+        encoder.copy_buffer_to_buffer(&chunk.buffer, offset, target, target_offset, size);
+    }
+
+    fn open_chunk(&mut self, device: &RenderDevice, min_size: u64) {
+        if let Some(chunk) = self.open.take() {
+            chunk.buffer.unmap();
+            self.in_flight.push(chunk.buffer);
+        }
+
+        let size = min_size.max(self.chunk_size);
+        let buffer = self
+            .free
+            .iter()
+            .position(|buffer| buffer.size() >= size)
+            .map(|index| self.free.swap_remove(index))
+            .unwrap_or_else(|| {
+                device.create_buffer(&BufferDescriptor {
+                    label: Some("gpu_staging_belt_chunk"),
+                    size,
+                    usage: BufferUsages::MAP_WRITE | BufferUsages::COPY_SRC,
+                    mapped_at_creation: true,
+                })
+            });
+
+        self.open = Some(OpenChunk {
+            buffer,
+            size,
+            offset: 0,
+        });
+    }
+
+    /// Unmaps the currently open chunk (if any) and marks it in-flight. Must be called once all
+    /// of this frame's [`write`](Self::write) calls have recorded their copies, and *before* the
+    /// command buffer containing them is submitted — wgpu rejects a mapped buffer used in a
+    /// submitted command.
+    pub fn finish(&mut self) {
+        if let Some(chunk) = self.open.take() {
+            chunk.buffer.unmap();
+            self.in_flight.push(chunk.buffer);
+        }
+    }
+
+    /// Starts remapping every in-flight buffer in the background so they can be handed out
+    /// again on a future frame. Must be called after [`finish`](Self::finish) and after the
+    /// command buffer containing the recorded copies has been submitted.
+    pub fn recall(&mut self) {
+        self.free.append(&mut self.recycled.lock().unwrap());
+
+        for buffer in self.in_flight.drain(..) {
+            let recycled = self.recycled.clone();
+            let recyclable = buffer.clone();
+            buffer
+                .slice(..)
+                .map_async(MapMode::Write, move |result| {
+                    if result.is_ok() {
+                        recycled.lock().unwrap().push(recyclable);
+                    }
+                });
+        }
+    }
+}
+
+#[inline]
+fn round_up(value: u64, alignment: u64) -> u64 {
+    ((value + alignment - 1) / alignment) * alignment
+}
+
+/// A destination buffer that grows to fit whatever's written to it and is kept up to date
+/// through a [`GpuStagingBelt`], rather than being reallocated and reserialized from scratch
+/// every frame. Shared by [`GpuListStorage`](super::GpuListStorage) and
+/// [`IndirectBuffer`](super::IndirectBuffer), which differ in buffer `usage`, label and staging
+/// chunk size.
+pub struct StagedBuffer {
+    buffer: Option<Buffer>,
+    belt: GpuStagingBelt,
+    usage: BufferUsages,
+    label: &'static str,
+    min_size: u64,
+}
+
+impl StagedBuffer {
+    pub fn new(chunk_size: u64, usage: BufferUsages, label: &'static str, min_size: u64) -> Self {
+        Self {
+            buffer: None,
+            belt: GpuStagingBelt::new(chunk_size),
+            usage,
+            label,
+            min_size,
+        }
+    }
+
+    /// Grows the destination buffer if needed, then stages `data` into it through the belt and
+    /// submits the copy.
+    pub fn write<T: bytemuck::Pod>(
+        &mut self,
+        device: &RenderDevice,
+        queue: &RenderQueue,
+        data: &[T],
+    ) {
+        if data.is_empty() {
+            return;
+        }
+
+        let size = std::mem::size_of_val(data) as u64;
+        if self.buffer.as_ref().map_or(true, |buffer| buffer.size() < size) {
+            self.buffer = Some(device.create_buffer(&BufferDescriptor {
+                label: Some(self.label),
+                size: size.max(self.min_size).next_power_of_two(),
+                usage: self.usage,
+                mapped_at_creation: false,
+            }));
+        }
+
+        let buffer = self.buffer.as_ref().expect("buffer was just allocated");
+        let mut encoder = device.create_command_encoder(&CommandEncoderDescriptor {
+            label: Some(self.label),
+        });
+        self.belt.write(device, &mut encoder, buffer, 0, data);
+        self.belt.finish();
+        queue.submit([encoder.finish()]);
+        self.belt.recall();
+    }
+
+    pub fn binding(&self) -> Option<BindingResource> {
+        self.buffer.as_ref().map(Buffer::as_entire_binding)
+    }
+
+    pub fn buffer(&self) -> Option<&Buffer> {
+        self.buffer.as_ref()
+    }
+}